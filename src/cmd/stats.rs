@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+use std::cmp::Ordering::Equal;
 use std::default::Default;
 use std::fmt;
 use std::from_str::{FromStr, from_str};
@@ -7,7 +9,7 @@ use std::str;
 
 use csv::{mod, ByteString};
 use csv::index::Indexed;
-use stats::{Commute, OnlineStats, MinMax, Unsorted, merge_all};
+use stats::{Commute, OnlineStats, MinMax, merge_all};
 
 use CliResult;
 use config::{Config, Delimiter};
@@ -20,7 +22,9 @@ Computes basic statistics on CSV data.
 Basic statistics includes mean, median, mode, standard deviation, max and
 min values. Note that some statistics are expensive to compute, so they must
 be enabled explicitly. By default, the following statistics are reported for
-*every* column in the CSV data: mean, max, min and standard deviation.
+*every* column in the CSV data: mean, max, min, min/max length, standard
+deviation, sum, variance, nullcount and sparsity (the fraction of samples
+that were empty).
 
 Computing statistics on a large file can be made much faster if you create
 an index for it first with 'xsv index'.
@@ -33,12 +37,33 @@ stats options:
                            See 'xsv select --help' for the format details.
                            This is provided here because piping 'xsv select'
                            into 'xsv stats' will disable the use of indexing.
-    --mode                 Show the mode.
+    --mode                 Show the mode. Multiple values tied for the most
+                           frequent are joined with a ';', and '*ALL' is
+                           shown when every value in the column is unique.
+                           This requires storing all CSV data in memory.
+    --antimode             Show the antimode, the least frequent value(s).
+                           Ties are joined with a ';', and '*ALL' is shown
+                           when every value in the column is unique.
                            This requires storing all CSV data in memory.
     --cardinality          Show the cardinality.
                            This requires storing all CSV data in memory.
     --median               Show the median.
                            This requires storing all CSV data in memory.
+    --quartiles            Show the quartiles, the interquartile range, the
+                           lower/upper fences and the quartile skewness.
+                           This requires storing all CSV data in memory.
+    --mad                  Show the median absolute deviation, a robust
+                           measure of dispersion.
+                           This requires storing all CSV data in memory.
+    --infer-dates          Try to parse columns as dates or datetimes
+                           (recognizing common formats like '2014-12-01' and
+                           '2014-12-01T12:00:00'). When a column infers as a
+                           date, min/max/mean are reported as dates too.
+    --binout <file>        Write the computed stats to <file> as a compact
+                           binary cache, in addition to the normal CSV
+                           output. Other xsv commands can load this cache
+                           to reuse the computed types, ranges and
+                           distributions without rescanning the data.
     --nulls                Include NULLs in the population size for computing
                            mean and standard deviation.
     -j, --jobs <arg>       The number of jobs to run in parallel.
@@ -64,8 +89,13 @@ struct Args {
     arg_input: Option<String>,
     flag_select: SelectColumns,
     flag_mode: bool,
+    flag_antimode: bool,
     flag_cardinality: bool,
     flag_median: bool,
+    flag_quartiles: bool,
+    flag_mad: bool,
+    flag_infer_dates: bool,
+    flag_binout: Option<String>,
     flag_nulls: bool,
     flag_jobs: uint,
     flag_output: Option<String>,
@@ -87,12 +117,23 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
             }
         }
     });
-    let stats = args.stats_to_records(stats);
+    let stat_headers = args.stat_headers();
+    let records = args.stats_to_records(stats);
+    if let Some(ref path) = args.flag_binout {
+        // Each cached row starts with the column's name, so its fields line
+        // up with `stat_headers` (which starts with "field").
+        let rows: Vec<Vec<String>> = headers.iter().zip(records.iter()).map(|(h, r)| {
+            let mut row = vec![String::from_utf8_lossy(h[]).into_string()];
+            row.push_all(r[]);
+            row
+        }).collect();
+        try!(io| write_binout(path[], stat_headers[], headers[], rows[]));
+    }
 
-    try!(csv| wtr.write(args.stat_headers().into_iter()));
-    for (header, stat) in headers.iter().zip(stats.into_iter()) {
+    try!(csv| wtr.write(stat_headers.into_iter()));
+    for (header, record) in headers.iter().zip(records.into_iter()) {
         let row = vec![header[]].into_iter()
-                                .chain(stat.iter().map(|f| f.as_bytes()));
+                                .chain(record.iter().map(|f| f.as_bytes()));
         try!(csv| wtr.write_bytes(row));
     }
     Ok(())
@@ -189,16 +230,32 @@ impl Args {
             dist: true,
             cardinality: self.flag_cardinality,
             median: self.flag_median,
+            quartiles: self.flag_quartiles,
+            mad: self.flag_mad,
             mode: self.flag_mode,
+            antimode: self.flag_antimode,
+            infer_dates: self.flag_infer_dates,
         }))
     }
 
     fn stat_headers(&self) -> Vec<String> {
         let mut fields = vec![
-            "field", "type", "min", "max", "mean", "stddev",
+            "field", "type", "min", "max", "min_length", "max_length",
+            "mean", "stddev", "sum", "variance", "nullcount", "sparsity",
         ];
         if self.flag_median { fields.push("median"); }
+        if self.flag_mad { fields.push("mad"); }
+        if self.flag_quartiles {
+            fields.push("q1");
+            fields.push("q2");
+            fields.push("q3");
+            fields.push("iqr");
+            fields.push("lower_fence");
+            fields.push("upper_fence");
+            fields.push("skewness");
+        }
         if self.flag_mode { fields.push("mode"); }
+        if self.flag_antimode { fields.push("antimode"); }
         if self.flag_cardinality { fields.push("cardinality"); }
         fields.into_iter().map(|s| s.to_string()).collect()
     }
@@ -211,7 +268,11 @@ struct WhichStats {
     dist: bool,
     cardinality: bool,
     median: bool,
+    quartiles: bool,
+    mad: bool,
     mode: bool,
+    antimode: bool,
+    infer_dates: bool,
 }
 
 impl Commute for WhichStats {
@@ -225,36 +286,66 @@ struct Stats {
     typ: FieldType,
     minmax: Option<TypedMinMax>,
     online: Option<OnlineStats>,
-    mode: Option<Unsorted<ByteString>>,
-    median: Option<Unsorted<f64>>,
+    // Frequency table, shared by --mode, --antimode and --cardinality (which
+    // is just freqs.len()) so a column isn't counted into two independent
+    // structures at once.
+    freqs: Option<HashMap<ByteString, u64>>,
+    // Raw numeric samples, sorted on demand in `to_record`. Shared by
+    // --median, --quartiles and --mad so indexed/parallel runs only ever
+    // carry one copy of the column's numbers around.
+    nums: Option<Vec<f64>>,
     which: WhichStats,
+    sum: f64,
+    nullcount: u64,
+    samples: u64,
+    lengths: MinMax<u64>,
 }
 
 impl Stats {
     fn new(which: WhichStats) -> Stats {
         let (mut minmax, mut online) = (None, None);
-        let (mut mode, mut median) = (None, None);
+        let mut nums = None;
+        let mut freqs = None;
         if which.range { minmax = Some(Default::default()); }
         if which.dist { online = Some(Default::default()); }
-        if which.mode || which.cardinality { mode = Some(Default::default()); }
-        if which.median { median = Some(Default::default()); }
+        if which.mode || which.antimode || which.cardinality {
+            freqs = Some(HashMap::new());
+        }
+        if which.median || which.quartiles || which.mad { nums = Some(vec![]); }
         Stats {
             typ: Default::default(),
             minmax: minmax,
             online: online,
-            mode: mode,
-            median: median,
+            freqs: freqs,
+            nums: nums,
             which: which,
+            sum: 0.0,
+            nullcount: 0,
+            samples: 0,
+            lengths: Default::default(),
         }
     }
 
     fn add(&mut self, sample: &[u8]) {
-        let sample_type = FieldType::from_sample(sample);
+        let sample_type = FieldType::from_sample(sample, self.which.infer_dates);
         self.typ.merge(sample_type);
 
+        self.samples += 1;
+        if sample_type.is_null() { self.nullcount += 1; }
+        if !sample.is_empty() { self.lengths.add(sample.len() as u64); }
+
         let t = self.typ;
         self.minmax.as_mut().map(|v| v.add(t, sample));
-        self.mode.as_mut().map(|v| v.add(ByteString::from_bytes(sample)));
+        if let Some(ref mut freqs) = self.freqs {
+            let key = ByteString::from_bytes(sample);
+            let found = match freqs.get_mut(&key) {
+                Some(count) => { *count += 1; true }
+                None => false,
+            };
+            if !found {
+                freqs.insert(key, 1u64);
+            }
+        }
         match self.typ {
             TUnknown => {}
             TNull => {}
@@ -266,8 +357,9 @@ impl Stats {
                     }
                 } else {
                     let n = from_bytes::<f64>(sample).unwrap();
-                    self.median.as_mut().map(|v| { v.add(n); });
+                    self.nums.as_mut().map(|v| { v.push(n); });
                     self.online.as_mut().map(|v| { v.add(n); });
+                    self.sum += n;
                 }
             }
             TInteger => {
@@ -277,8 +369,19 @@ impl Stats {
                     }
                 } else {
                     let n = from_bytes::<f64>(sample).unwrap();
-                    self.median.as_mut().map(|v| { v.add(n as f64); });
+                    self.nums.as_mut().map(|v| { v.push(n); });
+                    self.online.as_mut().map(|v| { v.add(n); });
+                    self.sum += n;
+                }
+            }
+            TDate | TDateTime => {
+                if sample_type.is_null() {
+                    if self.which.include_nulls {
+                        self.online.as_mut().map(|v| { v.add_null(); });
+                    }
+                } else if let Some((_, n)) = parse_date(str::from_utf8(sample).unwrap()) {
                     self.online.as_mut().map(|v| { v.add(n); });
+                    self.sum += n;
                 }
             }
         }
@@ -294,46 +397,117 @@ impl Stats {
             Some(mm) => { pieces.push(mm.0); pieces.push(mm.1); }
             None => { pieces.push(empty()); pieces.push(empty()); }
         }
+        match (self.lengths.min(), self.lengths.max()) {
+            (Some(min), Some(max)) => {
+                pieces.push(min.to_string());
+                pieces.push(max.to_string());
+            }
+            _ => { pieces.push(empty()); pieces.push(empty()); }
+        }
         if !self.typ.is_number() {
             pieces.push(empty()); pieces.push(empty());
+            pieces.push(empty()); pieces.push(empty());
         } else {
-            match self.online {
-                Some(ref v) => {
+            match (self.typ, self.online.as_ref()) {
+                // Dates/datetimes are stored as epoch days/seconds, so the
+                // mean must go through the same formatting as min/max in
+                // TypedMinMax::show. stddev/sum/variance are left blank:
+                // they're arithmetic over that raw epoch number, which
+                // isn't a meaningful date statistic.
+                (TDate, Some(v)) => {
+                    pieces.push(format_date(v.mean() as i64));
+                    pieces.push(empty()); pieces.push(empty()); pieces.push(empty());
+                }
+                (TDateTime, Some(v)) => {
+                    pieces.push(format_datetime(v.mean() as i64));
+                    pieces.push(empty()); pieces.push(empty()); pieces.push(empty());
+                }
+                (_, Some(v)) => {
                     pieces.push(v.mean().to_string());
                     pieces.push(v.stddev().to_string());
+                    pieces.push(self.sum.to_string());
+                    pieces.push((v.stddev() * v.stddev()).to_string());
+                }
+                (_, None) => {
+                    pieces.push(empty()); pieces.push(empty());
+                    pieces.push(empty()); pieces.push(empty());
                 }
-                None => { pieces.push(empty()); pieces.push(empty()); }
             }
         }
-        match self.median.as_mut().and_then(|v| v.median()) {
-            None => {
+        pieces.push(self.nullcount.to_string());
+        if self.samples == 0 {
+            pieces.push(empty());
+        } else {
+            pieces.push((self.nullcount as f64 / self.samples as f64).to_string());
+        }
+        // `--median`, `--quartiles` and `--mad` all read from the same
+        // sorted `nums` buffer, so sort it (once) here rather than keeping
+        // separately-populated buffers around. `unwrap_or(Equal)` keeps a
+        // stray NaN (e.g. a literal "NaN" cell, which parses as a float)
+        // from panicking the sort.
+        let sorted: Option<Vec<f64>> = self.nums.as_ref().map(|v| {
+            let mut s = v.clone();
+            s.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Equal));
+            s
+        });
+        match sorted.as_ref() {
+            Some(s) if !s.is_empty() => {
                 if self.which.median {
-                    pieces.push(empty());
+                    pieces.push(median_of_sorted(s[]).to_string());
                 }
             }
-            Some(v) => { pieces.push(v.to_string()); }
-        }
-        match self.mode.as_mut() {
-            None => {
-                if self.which.mode {
-                    pieces.push(empty());
-                }
-                if self.which.cardinality {
+            _ => {
+                if self.which.median {
                     pieces.push(empty());
                 }
             }
-            Some(ref mut v) => {
-                if self.which.mode {
-                    let lossy: |ByteString| -> String =
-                        |s| String::from_utf8_lossy(s[]).into_string();
-                    let mode = v.mode().map(lossy).unwrap_or("N/A".to_string());
-                    pieces.push(mode);
+        }
+        if self.which.mad {
+            match sorted.as_ref().and_then(|s| median_abs_deviation(s[])) {
+                None => { pieces.push(empty()); }
+                Some(mad) => { pieces.push(mad.to_string()); }
+            }
+        }
+        if self.which.quartiles {
+            match sorted.as_ref() {
+                Some(s) if !s.is_empty() => {
+                    let (q1, q2, q3) = quartiles_of_sorted(s[]);
+                    let iqr = q3 - q1;
+                    pieces.push(q1.to_string());
+                    pieces.push(q2.to_string());
+                    pieces.push(q3.to_string());
+                    pieces.push(iqr.to_string());
+                    pieces.push((q1 - 1.5 * iqr).to_string());
+                    pieces.push((q3 + 1.5 * iqr).to_string());
+                    if iqr == 0.0 {
+                        pieces.push(empty());
+                    } else {
+                        pieces.push(((q3 + q1 - 2.0 * q2) / iqr).to_string());
+                    }
                 }
-                if self.which.cardinality {
-                    pieces.push(v.cardinality().to_string());
+                _ => {
+                    for _ in range(0u, 7) { pieces.push(empty()); }
                 }
             }
         }
+        if self.which.mode {
+            match self.freqs.as_ref() {
+                Some(freqs) => { pieces.push(most_frequent(freqs)); }
+                None => { pieces.push(empty()); }
+            }
+        }
+        if self.which.antimode {
+            match self.freqs.as_ref() {
+                Some(freqs) => { pieces.push(least_frequent(freqs)); }
+                None => { pieces.push(empty()); }
+            }
+        }
+        if self.which.cardinality {
+            match self.freqs.as_ref() {
+                Some(freqs) => { pieces.push(freqs.len().to_string()); }
+                None => { pieces.push(empty()); }
+            }
+        }
         pieces
     }
 }
@@ -343,9 +517,27 @@ impl Commute for Stats {
         self.typ.merge(other.typ);
         self.minmax.merge(other.minmax);
         self.online.merge(other.online);
-        self.mode.merge(other.mode);
-        self.median.merge(other.median);
+        match (self.freqs.as_mut(), other.freqs) {
+            (Some(a), Some(b)) => {
+                for (k, v) in b.into_iter() {
+                    let found = match a.get_mut(&k) {
+                        Some(count) => { *count += v; true }
+                        None => false,
+                    };
+                    if !found { a.insert(k, v); }
+                }
+            }
+            _ => {}
+        }
+        match (self.nums.as_mut(), other.nums) {
+            (Some(a), Some(b)) => { a.extend(b.into_iter()); }
+            _ => {}
+        }
         self.which.merge(other.which);
+        self.sum.merge(other.sum);
+        self.nullcount.merge(other.nullcount);
+        self.samples.merge(other.samples);
+        self.lengths.merge(other.lengths);
     }
 }
 
@@ -356,10 +548,12 @@ enum FieldType {
     TUnicode,
     TFloat,
     TInteger,
+    TDate,
+    TDateTime,
 }
 
 impl FieldType {
-    fn from_sample(sample: &[u8]) -> FieldType {
+    fn from_sample(sample: &[u8], infer_dates: bool) -> FieldType {
         if sample.is_empty() {
             return TNull;
         }
@@ -367,13 +561,16 @@ impl FieldType {
             None => return TUnknown,
             Some(s) => s,
         };
+        if infer_dates {
+            if let Some((typ, _)) = parse_date(string) { return typ; }
+        }
         if let Some(_) = from_str::<i64>(string) { return TInteger; }
         if let Some(_) = from_str::<f64>(string) { return TFloat; }
         TUnicode
     }
 
     fn is_number(&self) -> bool {
-        *self == TFloat || *self == TInteger
+        *self == TFloat || *self == TInteger || *self == TDate || *self == TDateTime
     }
 
     fn is_null(&self) -> bool {
@@ -387,12 +584,18 @@ impl Commute for FieldType {
             (TUnicode, TUnicode) => TUnicode,
             (TFloat, TFloat) => TFloat,
             (TInteger, TInteger) => TInteger,
+            (TDate, TDate) => TDate,
+            (TDateTime, TDateTime) => TDateTime,
             // Null does not impact the type.
             (TNull, any) | (any, TNull) => any,
             // There's no way to get around an unknown.
             (TUnknown, _) | (_, TUnknown) => TUnknown,
             // Integers can degrate to floats.
             (TFloat, TInteger) | (TInteger, TFloat) => TFloat,
+            // Dates degrade to Unicode whenever they meet a counter-example,
+            // including a date of the other granularity.
+            (TDate, _) | (_, TDate) => TUnicode,
+            (TDateTime, _) | (_, TDateTime) => TUnicode,
             // Numbers can degrade to Unicode strings.
             (TUnicode, TFloat) | (TFloat, TUnicode) => TUnicode,
             (TUnicode, TInteger) | (TInteger, TUnicode) => TUnicode,
@@ -401,10 +604,11 @@ impl Commute for FieldType {
 }
 
 impl Default for FieldType {
-    // The default is the most specific type.
-    // Type inference proceeds by assuming the most specific type and then
-    // relaxing the type as counter-examples are found.
-    fn default() -> FieldType { TInteger }
+    // The default is the bottom of the lattice: unobserved. Unlike TInteger,
+    // TNull is transparent to merge() (see the Commute impl above), so the
+    // very first sample always determines the type instead of being
+    // clobbered into Unicode by a premature guess.
+    fn default() -> FieldType { TNull }
 }
 
 impl fmt::Show for FieldType {
@@ -415,6 +619,8 @@ impl fmt::Show for FieldType {
             TUnicode => write!(f, "Unicode"),
             TFloat => write!(f, "Float"),
             TInteger => write!(f, "Integer"),
+            TDate => write!(f, "Date"),
+            TDateTime => write!(f, "DateTime"),
         }
     }
 }
@@ -426,6 +632,7 @@ struct TypedMinMax {
     strings: MinMax<ByteString>,
     integers: MinMax<i64>,
     floats: MinMax<f64>,
+    timestamps: MinMax<f64>,
 }
 
 impl TypedMinMax {
@@ -449,6 +656,11 @@ impl TypedMinMax {
                             .unwrap();
                 self.integers.add(n);
             }
+            TDate | TDateTime => {
+                if let Some((_, n)) = parse_date(str::from_utf8(sample[]).unwrap()) {
+                    self.timestamps.add(n);
+                }
+            }
             _ => unreachable!(),
         }
     }
@@ -481,6 +693,24 @@ impl TypedMinMax {
                     _ => None
                 }
             }
+            TDate => {
+                match (self.timestamps.min(), self.timestamps.max()) {
+                    (Some(min), Some(max)) => {
+                        Some((format_date(min as i64), format_date(max as i64)))
+                    }
+                    _ => None
+                }
+            }
+            TDateTime => {
+                match (self.timestamps.min(), self.timestamps.max()) {
+                    (Some(min), Some(max)) => {
+                        Some((format_datetime(min as i64), format_datetime(max as i64)))
+                    }
+                    _ => None
+                }
+            }
+            // A column with no non-empty samples has no min/max.
+            TNull => None,
             _ => unreachable!(),
         }
     }
@@ -492,6 +722,7 @@ impl Default for TypedMinMax {
             strings: Default::default(),
             integers: Default::default(),
             floats: Default::default(),
+            timestamps: Default::default(),
         }
     }
 }
@@ -501,9 +732,468 @@ impl Commute for TypedMinMax {
         self.strings.merge(other.strings);
         self.integers.merge(other.integers);
         self.floats.merge(other.floats);
+        self.timestamps.merge(other.timestamps);
     }
 }
 
 fn from_bytes<T: FromStr>(bytes: &[u8]) -> Option<T> {
     str::from_utf8(bytes).and_then(from_str)
+}
+
+/// Computes the median absolute deviation of `sorted`, a robust measure of
+/// dispersion: the median of the absolute deviations from the median.
+/// `sorted` must already be sorted (see `to_record`).
+fn median_abs_deviation(sorted: &[f64]) -> Option<f64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let m = median_of_sorted(sorted);
+    let mut devs: Vec<f64> = sorted.iter().map(|&x| (x - m).abs()).collect();
+    devs.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Equal));
+    Some(median_of_sorted(devs.as_slice()))
+}
+
+fn median_of_sorted(sorted: &[f64]) -> f64 {
+    let len = sorted.len();
+    if len % 2 == 1 {
+        sorted[len / 2]
+    } else {
+        (sorted[len / 2 - 1] + sorted[len / 2]) / 2.0
+    }
+}
+
+/// Linearly interpolates the value at `pct` (in `[0, 1]`) of `sorted`,
+/// matching `median_of_sorted` at `pct == 0.5`.
+fn percentile_of_sorted(sorted: &[f64], pct: f64) -> f64 {
+    let len = sorted.len();
+    if len == 1 {
+        return sorted[0];
+    }
+    let rank = pct * (len - 1) as f64;
+    let lo = rank.floor() as uint;
+    let hi = rank.ceil() as uint;
+    if lo == hi {
+        return sorted[lo];
+    }
+    sorted[lo] + (sorted[hi] - sorted[lo]) * (rank - lo as f64)
+}
+
+/// Computes Q1, Q2 (median) and Q3 of `sorted` by linear interpolation.
+/// `sorted` must be non-empty.
+fn quartiles_of_sorted(sorted: &[f64]) -> (f64, f64, f64) {
+    (percentile_of_sorted(sorted, 0.25),
+     percentile_of_sorted(sorted, 0.50),
+     percentile_of_sorted(sorted, 0.75))
+}
+
+/// Every value in `freqs` occurs exactly once, i.e. the column is entirely
+/// made up of unique values.
+fn all_unique(freqs: &HashMap<ByteString, u64>) -> bool {
+    !freqs.is_empty() && freqs.values().all(|&count| count == 1)
+}
+
+/// Joins the values occurring `target` times in `freqs`, sorted for
+/// determinism.
+fn ties_at(freqs: &HashMap<ByteString, u64>, target: u64) -> String {
+    let mut vals: Vec<String> = freqs.iter()
+                                      .filter(|&(_, &count)| count == target)
+                                      .map(|(k, _)| String::from_utf8_lossy(k[]).into_string())
+                                      .collect();
+    vals.sort();
+    vals.connect(";")
+}
+
+fn most_frequent(freqs: &HashMap<ByteString, u64>) -> String {
+    if freqs.is_empty() {
+        return "N/A".to_string();
+    }
+    if all_unique(freqs) {
+        return "*ALL".to_string();
+    }
+    let max = *freqs.values().max().unwrap();
+    ties_at(freqs, max)
+}
+
+fn least_frequent(freqs: &HashMap<ByteString, u64>) -> String {
+    if freqs.is_empty() {
+        return "N/A".to_string();
+    }
+    if all_unique(freqs) {
+        return "*ALL".to_string();
+    }
+    let min = *freqs.values().min().unwrap();
+    ties_at(freqs, min)
+}
+
+/// Recognizes `s` as a date ('2014-12-01') or datetime
+/// ('2014-12-01T12:00:00' or '2014-12-01 12:00:00'), returning its type
+/// along with a numeric timestamp (days since the epoch for dates, seconds
+/// since the epoch for datetimes) that sorts and averages correctly.
+fn parse_date(s: &str) -> Option<(FieldType, f64)> {
+    let bytes = s.as_bytes();
+    if bytes.len() < 10 || bytes[4] != b'-' || bytes[7] != b'-' {
+        return None;
+    }
+    let (year, month, day) = match (from_str::<i64>(s.slice(0, 4)),
+                                     from_str::<uint>(s.slice(5, 7)),
+                                     from_str::<uint>(s.slice(8, 10))) {
+        (Some(y), Some(m), Some(d)) => (y, m, d),
+        _ => return None,
+    };
+    if month < 1 || month > 12 || day < 1 || day > 31 {
+        return None;
+    }
+    let days = days_from_civil(year, month, day);
+    if bytes.len() == 10 {
+        return Some((TDate, days as f64));
+    }
+    if bytes.len() == 19
+       && (bytes[10] == b'T' || bytes[10] == b' ')
+       && bytes[13] == b':' && bytes[16] == b':' {
+        let (hour, minute, second) = match (from_str::<uint>(s.slice(11, 13)),
+                                             from_str::<uint>(s.slice(14, 16)),
+                                             from_str::<uint>(s.slice(17, 19))) {
+            (Some(h), Some(m), Some(se)) => (h, m, se),
+            _ => return None,
+        };
+        if hour > 23 || minute > 59 || second > 59 {
+            return None;
+        }
+        let secs = days * 86400
+                 + (hour * 3600 + minute * 60 + second) as i64;
+        return Some((TDateTime, secs as f64));
+    }
+    None
+}
+
+/// Days since 1970-01-01 for the given (proleptic Gregorian) civil date.
+/// See Howard Hinnant's "chrono-Compatible Low-Level Date Algorithms".
+fn days_from_civil(y: i64, m: uint, d: uint) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = (if y >= 0 { y } else { y - 399 }) / 400;
+    let yoe = y - era * 400;
+    let mp = (m as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + d as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+/// The inverse of `days_from_civil`.
+fn civil_from_days(z: i64) -> (i64, uint, uint) {
+    let z = z + 719468;
+    let era = (if z >= 0 { z } else { z - 146096 }) / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let y = yoe + era * 400;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as uint;
+    let m = (if mp < 10 { mp + 3 } else { mp - 9 }) as uint;
+    (if m <= 2 { y + 1 } else { y }, m, d)
+}
+
+fn format_date(days: i64) -> String {
+    let (y, m, d) = civil_from_days(days);
+    format!("{:04}-{:02}-{:02}", y, m, d)
+}
+
+fn format_datetime(secs: i64) -> String {
+    let days = if secs >= 0 { secs / 86400 } else { (secs - 86399) / 86400 };
+    let secs_of_day = secs - days * 86400;
+    let (y, m, d) = civil_from_days(days);
+    format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+            y, m, d,
+            secs_of_day / 3600, (secs_of_day / 60) % 60, secs_of_day % 60)
+}
+
+/// A single CSV column's precomputed stats, as loaded from a `--binout`
+/// cache written by `write_binout`. `fields` holds one value per entry in
+/// the cache's stat headers, in the same order `xsv stats` prints them.
+pub struct StatsSnapshot {
+    pub header: ByteString,
+    pub fields: Vec<String>,
+}
+
+fn write_field<W: Writer>(w: &mut W, bytes: &[u8]) -> io::IoResult<()> {
+    try!(w.write_be_u64(bytes.len() as u64));
+    w.write(bytes)
+}
+
+fn read_field<R: Reader>(r: &mut R) -> io::IoResult<Vec<u8>> {
+    let len = try!(r.read_be_u64());
+    r.read_exact(len as uint)
+}
+
+/// Writes `stat_headers` (e.g. "mean", "stddev", ...) and one `record` per
+/// CSV column (aligned with `headers`) to `path` as a compact,
+/// length-prefixed binary cache (see `load_binout`).
+///
+/// This serializes the already-computed String output of `xsv stats`
+/// directly, rather than the internal `Stats` accumulator (which wraps
+/// external `stats` crate types not designed for serialization), so the
+/// cache format doesn't depend on those types growing Encodable/Decodable
+/// support.
+fn write_binout(path: &str, stat_headers: &[String], headers: &[ByteString],
+                records: &[Vec<String>]) -> io::IoResult<()> {
+    let mut f = try!(File::create(&Path::new(path)));
+    try!(f.write_be_u64(stat_headers.len() as u64));
+    for h in stat_headers.iter() {
+        try!(write_field(&mut f, h.as_bytes()));
+    }
+    try!(f.write_be_u64(headers.len() as u64));
+    for (header, record) in headers.iter().zip(records.iter()) {
+        try!(write_field(&mut f, header[]));
+        try!(f.write_be_u64(record.len() as u64));
+        for field in record.iter() {
+            try!(write_field(&mut f, field.as_bytes()));
+        }
+    }
+    Ok(())
+}
+
+/// Loads a binary stats cache previously written by `write_binout`,
+/// returning the stat column headers and one `StatsSnapshot` per CSV
+/// column.
+pub fn load_binout(path: &str) -> io::IoResult<(Vec<String>, Vec<StatsSnapshot>)> {
+    let mut f = try!(File::open(&Path::new(path)));
+    let nstat_headers = try!(f.read_be_u64());
+    let mut stat_headers = Vec::with_capacity(nstat_headers as uint);
+    for _ in range(0u64, nstat_headers) {
+        let bytes = try!(read_field(&mut f));
+        stat_headers.push(String::from_utf8_lossy(bytes[]).into_string());
+    }
+    let ncols = try!(f.read_be_u64());
+    let mut snapshots = Vec::with_capacity(ncols as uint);
+    for _ in range(0u64, ncols) {
+        let header = ByteString::from_bytes(try!(read_field(&mut f))[]);
+        let nfields = try!(f.read_be_u64());
+        let mut fields = Vec::with_capacity(nfields as uint);
+        for _ in range(0u64, nfields) {
+            let bytes = try!(read_field(&mut f));
+            fields.push(String::from_utf8_lossy(bytes[]).into_string());
+        }
+        snapshots.push(StatsSnapshot { header: header, fields: fields });
+    }
+    Ok((stat_headers, snapshots))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::default::Default;
+    use std::from_str::from_str;
+    use std::io::TempDir;
+    use std::str;
+    use stats::Commute;
+    use super::{ByteString, FieldType, Stats, WhichStats, TDate, TDateTime,
+                TInteger, civil_from_days, days_from_civil, parse_date,
+                median_of_sorted, quartiles_of_sorted,
+                median_abs_deviation, most_frequent, least_frequent,
+                write_binout, load_binout};
+
+    #[test]
+    fn date_inference_survives_the_default_type() {
+        // Regression test: FieldType::default() used to be TInteger, which
+        // forced the first sample of a genuine date column to immediately
+        // degrade to TUnicode instead of being recognized as TDate.
+        let mut typ: FieldType = Default::default();
+        typ.merge(FieldType::from_sample(b"2014-12-01", true));
+        assert_eq!(typ, TDate);
+        typ.merge(FieldType::from_sample(b"2015-01-02", true));
+        assert_eq!(typ, TDate);
+    }
+
+    #[test]
+    fn datetime_inference_survives_the_default_type() {
+        let mut typ: FieldType = Default::default();
+        typ.merge(FieldType::from_sample(b"2014-12-01T12:00:00", true));
+        assert_eq!(typ, TDateTime);
+    }
+
+    #[test]
+    fn digits_do_not_infer_as_dates_without_the_flag() {
+        let mut typ: FieldType = Default::default();
+        typ.merge(FieldType::from_sample(b"20141201", false));
+        assert_eq!(typ, TInteger);
+    }
+
+    #[test]
+    fn civil_days_round_trip() {
+        for &days in [0i64, 1, -1, 18262, -719162, 364].iter() {
+            let (y, m, d) = civil_from_days(days);
+            assert_eq!(days_from_civil(y, m, d), days);
+        }
+    }
+
+    #[test]
+    fn parse_date_rejects_garbage() {
+        assert_eq!(parse_date("not-a-date"), None);
+        assert_eq!(parse_date("2014-13-01"), None);
+        assert_eq!(parse_date("2014-12-01T25:00:00"), None);
+    }
+
+    #[test]
+    fn quartiles_agree_with_median_at_q2() {
+        let odd = vec![1.0f64, 2.0, 3.0, 4.0, 5.0];
+        let (_, q2, _) = quartiles_of_sorted(odd[]);
+        assert_eq!(q2, median_of_sorted(odd[]));
+
+        let even = vec![1.0f64, 2.0, 3.0, 4.0];
+        let (_, q2, _) = quartiles_of_sorted(even[]);
+        assert_eq!(q2, median_of_sorted(even[]));
+    }
+
+    #[test]
+    fn quartiles_interpolate_linearly() {
+        let sorted = vec![1.0f64, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0];
+        let (q1, q2, q3) = quartiles_of_sorted(sorted[]);
+        assert_eq!(q1, 2.75);
+        assert_eq!(q2, 4.5);
+        assert_eq!(q3, 6.25);
+    }
+
+    #[test]
+    fn mad_of_sorted_is_correct() {
+        let sorted = vec![1.0f64, 2.0, 3.0, 4.0, 5.0];
+        // median is 3; absolute deviations are [2, 1, 0, 1, 2], whose
+        // median is 1.
+        assert_eq!(median_abs_deviation(sorted[]), Some(1.0));
+    }
+
+    #[test]
+    fn mad_of_empty_is_none() {
+        let empty: Vec<f64> = vec![];
+        assert_eq!(median_abs_deviation(empty[]), None);
+    }
+
+    #[test]
+    fn most_and_least_frequent_report_ties_and_uniques() {
+        // Cardinality is meant to be derivable from this same map
+        // (freqs.len()) rather than a second, separately-counted buffer.
+        let mut freqs: HashMap<ByteString, u64> = HashMap::new();
+        freqs.insert(ByteString::from_bytes(b"a"), 2);
+        freqs.insert(ByteString::from_bytes(b"b"), 2);
+        freqs.insert(ByteString::from_bytes(b"c"), 1);
+        assert_eq!(most_frequent(&freqs), "a;b".to_string());
+        assert_eq!(least_frequent(&freqs), "c".to_string());
+        assert_eq!(freqs.len(), 3u);
+
+        let mut all_unique: HashMap<ByteString, u64> = HashMap::new();
+        all_unique.insert(ByteString::from_bytes(b"x"), 1);
+        all_unique.insert(ByteString::from_bytes(b"y"), 1);
+        assert_eq!(most_frequent(&all_unique), "*ALL".to_string());
+        assert_eq!(least_frequent(&all_unique), "*ALL".to_string());
+
+        let empty: HashMap<ByteString, u64> = HashMap::new();
+        assert_eq!(most_frequent(&empty), "N/A".to_string());
+        assert_eq!(least_frequent(&empty), "N/A".to_string());
+    }
+
+    #[test]
+    fn binout_round_trips() {
+        let dir = TempDir::new("xsv-stats-test").unwrap();
+        let path = dir.path().join("stats.bin");
+        let path_str = path.as_str().unwrap();
+
+        let stat_headers = vec!["field".to_string(), "type".to_string()];
+        let headers = vec![ByteString::from_bytes(b"a"), ByteString::from_bytes(b"b")];
+        let records = vec![
+            vec!["a".to_string(), "Integer".to_string()],
+            vec!["b".to_string(), "Unicode".to_string()],
+        ];
+
+        write_binout(path_str, stat_headers[], headers[], records[]).unwrap();
+        let (loaded_headers, snapshots) = load_binout(path_str).unwrap();
+
+        assert_eq!(loaded_headers, stat_headers);
+        assert_eq!(snapshots.len(), 2u);
+        for i in range(0u, 2) {
+            assert_eq!(str::from_utf8(snapshots[i].header[]).unwrap(),
+                       str::from_utf8(headers[i][]).unwrap());
+            assert_eq!(snapshots[i].fields, records[i]);
+        }
+    }
+
+    // Only the always-on columns (range/dist) are enabled; every other flag
+    // in WhichStats is off for these tests.
+    fn always_on() -> WhichStats {
+        WhichStats {
+            include_nulls: false,
+            range: true,
+            dist: true,
+            cardinality: false,
+            median: false,
+            quartiles: false,
+            mad: false,
+            mode: false,
+            antimode: false,
+            infer_dates: false,
+        }
+    }
+
+    #[test]
+    fn sum_variance_nullcount_and_sparsity_end_to_end() {
+        let mut whole = Stats::new(always_on());
+        for sample in ["1", "2", "", "3"].iter() {
+            whole.add(sample.as_bytes());
+        }
+        let record = whole.to_record();
+        // type, min, max, min_length, max_length, mean, stddev,
+        // sum, variance, nullcount, sparsity ("field" is added by `run`)
+        assert_eq!(record[7], "6".to_string());
+        // variance is reported as the square of stddev.
+        let stddev = from_str::<f64>(record[6][]).unwrap();
+        let variance = from_str::<f64>(record[8][]).unwrap();
+        assert!((variance - stddev * stddev).abs() < 1e-12);
+        assert_eq!(record[9], "1".to_string());
+        assert_eq!(record[10], "0.25".to_string());
+
+        // Splitting the same samples across two chunks and merging them
+        // (as the indexed/parallel path does) must agree with a single
+        // sequential run.
+        let mut a = Stats::new(always_on());
+        a.add(b"1");
+        a.add(b"2");
+        let mut b = Stats::new(always_on());
+        b.add(b"");
+        b.add(b"3");
+        a.merge(b);
+        assert_eq!(a.to_record(), record);
+    }
+
+    #[test]
+    fn min_max_length_end_to_end_including_a_date_column() {
+        let mut stats = Stats::new(always_on());
+        for sample in ["x", "", "xyz", "xy"].iter() {
+            stats.add(sample.as_bytes());
+        }
+        let record = stats.to_record();
+        assert_eq!(record[3], "1".to_string());
+        assert_eq!(record[4], "3".to_string());
+
+        // Merging chunks must agree with a single sequential run here too.
+        let mut a = Stats::new(always_on());
+        a.add(b"x");
+        a.add(b"");
+        let mut b = Stats::new(always_on());
+        b.add(b"xyz");
+        b.add(b"xy");
+        a.merge(b);
+        assert_eq!(a.to_record(), record);
+
+        // A date column's min/max length is just its string length, and
+        // (per the --infer-dates fix) its mean must come back as a date.
+        let mut which = always_on();
+        which.infer_dates = true;
+        let mut dates = Stats::new(which);
+        dates.add(b"2014-12-01");
+        dates.add(b"2014-12-03");
+        let record = dates.to_record();
+        assert_eq!(record[0], "Date".to_string());
+        assert_eq!(record[1], "2014-12-01".to_string());
+        assert_eq!(record[2], "2014-12-03".to_string());
+        assert_eq!(record[3], "10".to_string());
+        assert_eq!(record[4], "10".to_string());
+        assert_eq!(record[5], "2014-12-02".to_string());
+    }
 }
\ No newline at end of file